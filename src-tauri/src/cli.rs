@@ -0,0 +1,179 @@
+//! Headless CLI entrypoint.
+//!
+//! Invoking the binary with a subcommand (`keys ...`, `backend ...`) runs
+//! headless instead of launching Tauri, reusing the same `SecretStore`,
+//! `master_key`, and `supervisor` code paths the GUI commands use. This
+//! covers scripting, CI provisioning, and server deployments with no window
+//! system available.
+
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand};
+
+use crate::master_key;
+use crate::metrics::{self, Metrics};
+use crate::secure_storage::{self, ApiKeyData};
+use crate::supervisor::{self, BackendEvents, BackendState, BackendStatus};
+
+#[derive(Parser)]
+#[command(name = "echov2", about = "EchoV2 desktop app")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Manage stored provider API keys
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+    /// Control the backend process
+    Backend {
+        #[command(subcommand)]
+        action: BackendAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum KeysAction {
+    /// Store an API key for a provider (prompts for the secret and master
+    /// passphrase on stdin, so neither lands in shell history)
+    Set {
+        provider: String,
+        #[arg(long)]
+        custom_endpoint: Option<String>,
+    },
+    /// Print the stored API key for a provider
+    Get { provider: String },
+    /// List providers with a stored key
+    List,
+    /// Remove a provider's stored key
+    Rm { provider: String },
+}
+
+#[derive(Subcommand)]
+pub enum BackendAction {
+    /// Start the backend supervisor in the foreground (Ctrl+C to stop)
+    Start,
+    /// Stop a backend started by a previous `backend start`
+    Stop,
+    /// Report whether the backend is running and healthy
+    Status,
+}
+
+/// Runs the headless CLI. Callers should check `cli.command.is_some()`
+/// before invoking this and fall back to the Tauri GUI otherwise.
+pub async fn run(cli: Cli) -> Result<(), String> {
+    let command = cli
+        .command
+        .ok_or_else(|| "run() requires a subcommand".to_string())?;
+
+    match command {
+        Commands::Keys { action } => run_keys(action).await,
+        Commands::Backend { action } => run_backend(action).await,
+    }
+}
+
+fn prompt_secret(prompt: &str) -> Result<String, String> {
+    use std::io::Write;
+    eprint!("{}", prompt);
+    std::io::stderr()
+        .flush()
+        .map_err(|e| format!("Failed to flush stderr: {}", e))?;
+
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read from stdin: {}", e))?;
+    Ok(line.trim_end_matches(['\n', '\r']).to_string())
+}
+
+async fn run_keys(action: KeysAction) -> Result<(), String> {
+    let store = secure_storage::backend_from_env()?;
+
+    match action {
+        KeysAction::Set {
+            provider,
+            custom_endpoint,
+        } => {
+            let api_key = prompt_secret(&format!("API key for {}: ", provider))?;
+            let passphrase = prompt_secret("Master passphrase: ")?;
+            let salt = master_key::load_or_create_salt()?;
+            let key = master_key::derive_key(&passphrase, &salt)?;
+
+            let data = ApiKeyData {
+                provider: provider.clone(),
+                api_key,
+                custom_endpoint,
+            };
+            let sealed = master_key::seal(&key, &data)?;
+            store.set(&provider, &sealed)?;
+            println!("Stored API key for {}", provider);
+            Ok(())
+        }
+        KeysAction::Get { provider } => {
+            let passphrase = prompt_secret("Master passphrase: ")?;
+            let salt = master_key::load_or_create_salt()?;
+            let key = master_key::derive_key(&passphrase, &salt)?;
+
+            match store.get(&provider)? {
+                Some(sealed) => {
+                    let data = master_key::unseal(&key, &sealed)?;
+                    println!("{}", data.api_key);
+                    Ok(())
+                }
+                None => Err(format!("No API key stored for {}", provider)),
+            }
+        }
+        KeysAction::List => {
+            for provider in store.list()? {
+                println!("{}", provider);
+            }
+            Ok(())
+        }
+        KeysAction::Rm { provider } => {
+            store.delete(&provider)?;
+            println!("Removed API key for {}", provider);
+            Ok(())
+        }
+    }
+}
+
+struct CliBackendEvents;
+
+impl BackendEvents for CliBackendEvents {
+    fn on_status(&self, status: BackendStatus) {
+        println!("[backend] status: {:?}", status);
+    }
+
+    fn on_log(&self, line: &str) {
+        println!("[backend] {}", line);
+    }
+}
+
+async fn run_backend(action: BackendAction) -> Result<(), String> {
+    match action {
+        BackendAction::Start => {
+            let state = Arc::new(BackendState::default());
+            let metrics = Arc::new(Metrics::default());
+            metrics::maybe_start_http_server(metrics.clone());
+            supervisor::spawn_supervised(Arc::new(CliBackendEvents), metrics, state.clone());
+
+            println!("Backend supervisor running in the foreground; press Ctrl+C to stop.");
+            tokio::signal::ctrl_c()
+                .await
+                .map_err(|e| format!("Failed to listen for Ctrl+C: {}", e))?;
+
+            supervisor::stop(&state);
+            Ok(())
+        }
+        BackendAction::Stop => supervisor::stop_external(),
+        BackendAction::Status => {
+            let status = supervisor::query_external_status().await;
+            println!("{:?}", status);
+            Ok(())
+        }
+    }
+}
@@ -1,266 +1,294 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use std::collections::HashMap;
-use tauri::{Manager, State, command};
-use tokio::time::sleep;
-use keyring::Entry;
-use serde::{Deserialize, Serialize};
+mod cli;
+mod master_key;
+mod metrics;
+mod secure_storage;
+mod supervisor;
 
-const SERVICE_NAME: &str = "com.echov2.app";
-const API_KEY_PREFIX: &str = "api_key";
+use std::sync::Arc;
+use clap::Parser;
+use tauri::{AppHandle, Manager, State, command};
+use zeroize::Zeroizing;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct SecureStorageError {
-    message: String,
-}
+use cli::Cli;
+use master_key::MasterKeyState;
+use metrics::{Metrics, SecretOp};
+use secure_storage::{ApiKeyData, SecretStore};
+use supervisor::{BackendEvents, BackendState, BackendStatus};
 
-impl std::fmt::Display for SecureStorageError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.message)
-    }
-}
+/// Forwards supervisor events to the frontend as Tauri events.
+struct TauriBackendEvents(AppHandle);
 
-impl std::error::Error for SecureStorageError {}
+impl BackendEvents for TauriBackendEvents {
+    fn on_status(&self, status: BackendStatus) {
+        let _ = self.0.emit_all("backend-status", status);
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ApiKeyData {
-    provider: String,
-    api_key: String,
-    custom_endpoint: Option<String>,
+    fn on_log(&self, line: &str) {
+        let _ = self.0.emit_all("backend-log", line);
+    }
 }
 
-// Secure storage commands
+// Secure storage commands. These route through the `SecretStore` trait
+// object held in managed state, rather than talking to the OS keyring
+// directly, so the active backend can be swapped at startup. The actual
+// secret payload is sealed under the session master key before it ever
+// reaches the backend; see `master_key`.
 #[command]
 async fn store_api_key(
+    store: State<'_, Arc<dyn SecretStore>>,
+    master_key: State<'_, MasterKeyState>,
+    metrics: State<'_, Arc<Metrics>>,
     provider: String,
     api_key: String,
     custom_endpoint: Option<String>,
 ) -> Result<(), String> {
-    let key_name = format!("{}_{}", API_KEY_PREFIX, provider);
-    
-    match Entry::new(SERVICE_NAME, &key_name) {
-        Ok(entry) => {
-            let data = ApiKeyData {
-                provider: provider.clone(),
-                api_key,
-                custom_endpoint,
-            };
-            
-            let serialized = serde_json::to_string(&data)
-                .map_err(|e| format!("Failed to serialize API key data: {}", e))?;
-            
-            entry.set_password(&serialized)
-                .map_err(|e| format!("Failed to store API key for {}: {}", provider, e))?;
-            
-            println!("API key stored securely for provider: {}", provider);
-            Ok(())
-        }
-        Err(e) => Err(format!("Failed to create keyring entry for {}: {}", provider, e)),
-    }
+    let key = master_key.require_key()?;
+    let data = ApiKeyData {
+        provider: provider.clone(),
+        api_key,
+        custom_endpoint,
+    };
+    let sealed = master_key::seal(&key, &data)?;
+
+    let result = store.set(&provider, &sealed);
+    metrics.record_secret_op(SecretOp::Set, result.is_ok());
+    result?;
+    println!("API key stored securely for provider: {}", provider);
+    Ok(())
 }
 
 #[command]
-async fn get_api_key(provider: String) -> Result<Option<ApiKeyData>, String> {
-    let key_name = format!("{}_{}", API_KEY_PREFIX, provider);
-    
-    match Entry::new(SERVICE_NAME, &key_name) {
-        Ok(entry) => {
-            match entry.get_password() {
-                Ok(password) => {
-                    let data: ApiKeyData = serde_json::from_str(&password)
-                        .map_err(|e| format!("Failed to deserialize API key data: {}", e))?;
-                    Ok(Some(data))
-                }
-                Err(keyring::Error::NoEntry) => Ok(None),
-                Err(e) => Err(format!("Failed to retrieve API key for {}: {}", provider, e)),
-            }
-        }
-        Err(e) => Err(format!("Failed to create keyring entry for {}: {}", provider, e)),
+async fn get_api_key(
+    store: State<'_, Arc<dyn SecretStore>>,
+    master_key: State<'_, MasterKeyState>,
+    metrics: State<'_, Arc<Metrics>>,
+    provider: String,
+) -> Result<Option<ApiKeyData>, String> {
+    let key = master_key.require_key()?;
+    let result = store.get(&provider);
+    metrics.record_secret_op(SecretOp::Get, result.is_ok());
+
+    match result? {
+        Some(sealed) => Ok(Some(master_key::unseal(&key, &sealed)?)),
+        None => Ok(None),
     }
 }
 
 #[command]
-async fn delete_api_key(provider: String) -> Result<(), String> {
-    let key_name = format!("{}_{}", API_KEY_PREFIX, provider);
-    
-    match Entry::new(SERVICE_NAME, &key_name) {
-        Ok(entry) => {
-            match entry.delete_password() {
-                Ok(()) => {
-                    println!("API key deleted for provider: {}", provider);
-                    Ok(())
-                }
-                Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
-                Err(e) => Err(format!("Failed to delete API key for {}: {}", provider, e)),
-            }
-        }
-        Err(e) => Err(format!("Failed to create keyring entry for {}: {}", provider, e)),
-    }
+async fn delete_api_key(
+    store: State<'_, Arc<dyn SecretStore>>,
+    metrics: State<'_, Arc<Metrics>>,
+    provider: String,
+) -> Result<(), String> {
+    let result = store.delete(&provider);
+    metrics.record_secret_op(SecretOp::Delete, result.is_ok());
+    result?;
+    println!("API key deleted for provider: {}", provider);
+    Ok(())
 }
 
 #[command]
-async fn list_stored_providers() -> Result<Vec<String>, String> {
-    // Note: Keyring doesn't provide enumeration, so we'll check common providers
-    let common_providers = vec!["openai", "anthropic", "google", "ollama"];
-    let mut stored_providers = Vec::new();
-    
-    for provider in common_providers {
-        if let Ok(Some(_)) = get_api_key(provider.to_string()).await {
-            stored_providers.push(provider.to_string());
-        }
-    }
-    
-    Ok(stored_providers)
+async fn list_stored_providers(
+    store: State<'_, Arc<dyn SecretStore>>,
+    metrics: State<'_, Arc<Metrics>>,
+) -> Result<Vec<String>, String> {
+    let result = store.list();
+    metrics.record_secret_op(SecretOp::List, result.is_ok());
+    result
 }
 
 #[command]
 async fn migrate_from_localstorage(
+    store: State<'_, Arc<dyn SecretStore>>,
+    master_key: State<'_, MasterKeyState>,
+    metrics: State<'_, Arc<Metrics>>,
     provider: String,
     api_key: String,
     custom_endpoint: Option<String>,
 ) -> Result<(), String> {
     // Store in secure storage and return success
-    store_api_key(provider, api_key, custom_endpoint).await
+    store_api_key(store, master_key, metrics, provider, api_key, custom_endpoint).await
 }
 
-// Backend process state
-#[derive(Default)]
-struct BackendState {
-    process: Arc<Mutex<Option<Child>>>,
+// Master passphrase commands.
+#[command]
+async fn unlock(master_key: State<'_, MasterKeyState>, passphrase: String) -> Result<(), String> {
+    let salt = master_key::load_or_create_salt()?;
+    let key = master_key::derive_key(&passphrase, &salt)?;
+    master_key.set(key);
+    Ok(())
 }
 
-// Health check the backend
-async fn wait_for_backend() -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    let mut attempts = 0;
-    let max_attempts = 30; // Wait up to 30 seconds
-    
-    while attempts < max_attempts {
-        match client.get("http://localhost:8000/health").send().await {
-            Ok(response) if response.status().is_success() => {
-                println!("Backend is ready!");
-                return Ok(());
-            }
-            _ => {
-                attempts += 1;
-                sleep(Duration::from_secs(1)).await;
-            }
+#[command]
+async fn lock(master_key: State<'_, MasterKeyState>) -> Result<(), String> {
+    master_key.clear();
+    Ok(())
+}
+
+#[command]
+async fn is_unlocked(master_key: State<'_, MasterKeyState>) -> Result<bool, String> {
+    Ok(master_key.is_unlocked())
+}
+
+/// Best-effort: re-seals `records` under `old_key` and writes them back, for
+/// unwinding a `change_passphrase` that failed partway through rewriting
+/// providers under the new key. Swallows errors since this only runs while
+/// already reporting a failure and there's no better recovery to fall back to.
+fn rollback_to_old_key(store: &Arc<dyn SecretStore>, old_key: &Zeroizing<[u8; 32]>, records: &[ApiKeyData]) {
+    for data in records {
+        if let Ok(sealed) = master_key::seal(old_key, data) {
+            let _ = store.set(&data.provider, &sealed);
         }
     }
-    
-    Err("Backend failed to start within timeout".into())
 }
 
-// Start the backend process
-async fn start_backend(backend_state: &BackendState) -> Result<(), Box<dyn std::error::Error>> {
-    let mut process_guard = backend_state.process.lock().unwrap();
-    
-    if process_guard.is_some() {
-        return Ok(()); // Already running
+#[command]
+async fn change_passphrase(
+    store: State<'_, Arc<dyn SecretStore>>,
+    master_key: State<'_, MasterKeyState>,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<(), String> {
+    let old_salt = master_key::load_or_create_salt()?;
+    let old_key = master_key::derive_key(&old_passphrase, &old_salt)?;
+
+    // Decrypt every stored record under the old key before rotating, so a
+    // wrong old passphrase fails loudly instead of silently corrupting data.
+    let mut records = Vec::new();
+    for provider in store.list()? {
+        if let Some(sealed) = store.get(&provider)? {
+            records.push(master_key::unseal(&old_key, &sealed)?);
+        }
     }
-    
-    // Get the path to the backend executable
-    let backend_path = if cfg!(debug_assertions) {
-        // In development, use the Python script
-        let backend_dir = std::env::current_exe()?
-            .parent()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .join("backend");
-        
-        println!("Starting backend in development mode from: {:?}", backend_dir);
-        
-        // Start Python backend directly
-        let mut child = Command::new("python")
-            .arg("main.py")
-            .current_dir(backend_dir)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
-        
-        *process_guard = Some(child);
-        drop(process_guard);
-        
-        // Wait for backend to be ready
-        wait_for_backend().await?;
-        
-        return Ok(());
-    } else {
-        // In production, use the bundled executable
-        let app_dir = std::env::current_exe()?
-            .parent()
-            .unwrap();
-        
-        let backend_executable = app_dir.join("echov2-backend");
-        
-        if !backend_executable.exists() {
-            return Err(format!("Backend executable not found at: {:?}", backend_executable).into());
+
+    // Stage the re-seal fully in memory first: `seal()` never touches disk,
+    // so a failure there leaves nothing to unwind. `old_salt` stays the
+    // persisted salt until every provider has been durably rewritten under
+    // `new_key`, so at any point before that, `old_key` is still derivable
+    // and nothing is stranded.
+    let new_salt = master_key::generate_salt();
+    let new_key = master_key::derive_key(&new_passphrase, &new_salt)?;
+    let resealed: Vec<ApiKeyData> = records
+        .iter()
+        .map(|data| master_key::seal(&new_key, data))
+        .collect::<Result<_, _>>()?;
+
+    let mut written = 0usize;
+    for data in &resealed {
+        if let Err(e) = store.set(&data.provider, data) {
+            // Roll back every provider already written under `new_key` in
+            // this loop, re-sealing them back under `old_key` -- the salt on
+            // disk is still `old_salt`, so `old_key` remains derivable and
+            // nothing ends up split across two keys.
+            rollback_to_old_key(store.inner(), &old_key, &records[..written]);
+            return Err(format!(
+                "Failed to re-seal provider {} during passphrase change (rolled back): {}",
+                data.provider, e
+            ));
         }
-        
-        println!("Starting backend from: {:?}", backend_executable);
-        
-        let mut child = Command::new(&backend_executable)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
-        
-        *process_guard = Some(child);
-        drop(process_guard);
-        
-        // Wait for backend to be ready
-        wait_for_backend().await?;
-        
-        Ok(())
+        written += 1;
     }
-}
 
-// Stop the backend process
-fn stop_backend(backend_state: &BackendState) {
-    let mut process_guard = backend_state.process.lock().unwrap();
-    
-    if let Some(mut child) = process_guard.take() {
-        println!("Stopping backend process...");
-        let _ = child.kill();
-        let _ = child.wait();
+    // Only commit the new salt once every provider is durably re-sealed
+    // under `new_key`. If this fails, undo the loop above so no record is
+    // left re-sealed under a key whose salt was never persisted.
+    if let Err(e) = master_key::persist_salt(&new_salt) {
+        rollback_to_old_key(store.inner(), &old_key, &records);
+        return Err(format!(
+            "Failed to persist rotated salt during passphrase change (rolled back): {}",
+            e
+        ));
     }
+
+    master_key.set(new_key);
+    Ok(())
+}
+
+// Backend supervision commands. The supervisor owns the child process and
+// keeps it running; these commands let the frontend observe and nudge it.
+#[command]
+async fn restart_backend(
+    app: AppHandle,
+    state: State<'_, Arc<BackendState>>,
+    metrics: State<'_, Arc<Metrics>>,
+) -> Result<(), String> {
+    supervisor::stop(&state);
+    supervisor::spawn_supervised(
+        Arc::new(TauriBackendEvents(app)),
+        metrics.inner().clone(),
+        state.inner().clone(),
+    );
+    Ok(())
+}
+
+#[command]
+async fn get_backend_status(state: State<'_, Arc<BackendState>>) -> Result<BackendStatus, String> {
+    Ok(state.status())
+}
+
+#[command]
+async fn get_metrics(metrics: State<'_, Arc<Metrics>>) -> Result<metrics::MetricsSnapshot, String> {
+    Ok(metrics.snapshot())
 }
 
 #[tokio::main]
 async fn main() {
-    let backend_state = BackendState::default();
-    
-    // Start the backend process
-    if let Err(e) = start_backend(&backend_state).await {
-        eprintln!("Failed to start backend: {}", e);
-        std::process::exit(1);
+    let cli = Cli::parse();
+    if cli.command.is_some() {
+        if let Err(e) = cli::run(cli).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
     }
-    
-    let backend_state_for_app = Arc::new(backend_state);
-    let backend_state_for_cleanup = backend_state_for_app.clone();
-    
+
+    let backend_state = Arc::new(BackendState::default());
+    let backend_state_for_cleanup = backend_state.clone();
+
+    let secret_store: Arc<dyn SecretStore> = match secure_storage::backend_from_env() {
+        Ok(store) => Arc::from(store),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let metrics = Arc::new(Metrics::default());
+    metrics::maybe_start_http_server(metrics.clone());
+
     tauri::Builder::default()
-        .manage(backend_state_for_app)
+        .manage(backend_state)
+        .manage(secret_store)
+        .manage(MasterKeyState::default())
+        .manage(metrics)
         .invoke_handler(tauri::generate_handler![
             store_api_key,
             get_api_key,
             delete_api_key,
             list_stored_providers,
-            migrate_from_localstorage
+            migrate_from_localstorage,
+            unlock,
+            lock,
+            is_unlocked,
+            change_passphrase,
+            restart_backend,
+            get_backend_status,
+            get_metrics
         ])
-        .setup(|_app| {
+        .setup(|app| {
             println!("EchoV2 frontend started successfully!");
+            let backend_state = app.state::<Arc<BackendState>>().inner().clone();
+            let metrics = app.state::<Arc<Metrics>>().inner().clone();
+            let events = Arc::new(TauriBackendEvents(app.handle()));
+            metrics::maybe_start_event_emitter(app.handle(), metrics.clone());
+            supervisor::spawn_supervised(events, metrics, backend_state);
             Ok(())
         })
         .on_window_event(move |event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event.event() {
                 println!("Application closing, stopping backend...");
-                stop_backend(&backend_state_for_cleanup);
+                supervisor::stop(&backend_state_for_cleanup);
             }
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,211 @@
+//! App-level master passphrase.
+//!
+//! API keys handed to a `SecretStore` backend are sealed with a session key
+//! derived from a user passphrase before they ever reach the backend, so a
+//! plaintext-JSON keyring entry (or an unencrypted backend) still can't be
+//! read by another process running as the same user. The derived key lives
+//! in memory only for the session: [`unlock`] loads it, [`MasterKeyState::clear`]
+//! (the `lock` command) zeroizes it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::secure_storage::ApiKeyData;
+
+pub const LOCKED_ERROR: &str = "Secrets are locked. Call unlock with your passphrase first.";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+// Argon2id parameters. These favor the default Argon2 recommendation over
+// raw speed; override via env if a given host needs to trade off memory.
+const ARGON2_MEM_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+const ENV_SALT_PATH: &str = "ECHOV2_MASTER_SALT_PATH";
+
+#[derive(Serialize, Deserialize)]
+struct SaltFile {
+    salt: String, // base64
+}
+
+#[derive(Serialize, Deserialize)]
+struct SealedPayload {
+    nonce: String,      // base64
+    ciphertext: String, // base64
+}
+
+/// Session-held master key, managed as Tauri state.
+#[derive(Default)]
+pub struct MasterKeyState {
+    key: Mutex<Option<Zeroizing<[u8; 32]>>>,
+}
+
+impl MasterKeyState {
+    pub fn set(&self, key: Zeroizing<[u8; 32]>) {
+        *self.key.lock().unwrap() = Some(key);
+    }
+
+    /// Zeroizes and drops the in-memory key (the `lock` command).
+    pub fn clear(&self) {
+        *self.key.lock().unwrap() = None;
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.key.lock().unwrap().is_some()
+    }
+
+    pub fn require_key(&self) -> Result<Zeroizing<[u8; 32]>, String> {
+        self.key
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| LOCKED_ERROR.to_string())
+    }
+}
+
+fn salt_path() -> PathBuf {
+    if let Ok(path) = std::env::var(ENV_SALT_PATH) {
+        return PathBuf::from(path);
+    }
+    let base = dirs::config_dir().unwrap_or_else(std::env::temp_dir);
+    Path::new(&base).join("echov2").join("master.salt.json")
+}
+
+/// Loads the persisted salt, generating and persisting a new random one on
+/// first run.
+pub fn load_or_create_salt() -> Result<Vec<u8>, String> {
+    let path = salt_path();
+
+    if path.exists() {
+        let raw = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read master salt file: {}", e))?;
+        let file: SaltFile = serde_json::from_str(&raw)
+            .map_err(|e| format!("Failed to parse master salt file: {}", e))?;
+        return base64_decode(&file.salt);
+    }
+
+    let salt = generate_salt();
+    persist_salt(&salt)?;
+    Ok(salt)
+}
+
+/// Generates a fresh random salt without touching disk. Callers that need
+/// to rotate the passphrase stage everything (re-sealing every record under
+/// the new key) before calling [`persist_salt`], so a crash or a failed
+/// re-seal partway through never leaves the on-disk salt ahead of the
+/// records it's supposed to protect.
+pub fn generate_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Persists `salt` as the master salt file, replacing whatever was there.
+/// Writes to a temp file in the same directory and renames it into place so
+/// a crash mid-write can't leave a truncated or partially-written salt file.
+pub fn persist_salt(salt: &[u8]) -> Result<(), String> {
+    let path = salt_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let file = SaltFile {
+        salt: base64_encode(salt),
+    };
+    let serialized = serde_json::to_string(&file)
+        .map_err(|e| format!("Failed to serialize master salt file: {}", e))?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serialized)
+        .map_err(|e| format!("Failed to write master salt file: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to replace master salt file: {}", e))
+}
+
+/// Derives a 256-bit key from `passphrase` and `salt` with Argon2id.
+pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, String> {
+    let params = Params::new(ARGON2_MEM_KIB, ARGON2_ITERATIONS, ARGON2_PARALLELISM, Some(32))
+        .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key_bytes = Zeroizing::new([0u8; 32]);
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, key_bytes.as_mut())
+        .map_err(|e| format!("Failed to derive master key: {}", e))?;
+    Ok(key_bytes)
+}
+
+/// Encrypts `data` under `key`, returning an `ApiKeyData` whose `api_key`
+/// field carries the sealed envelope (nonce + ciphertext) in place of the
+/// plaintext secret. This is what actually gets handed to a `SecretStore`.
+pub fn seal(key: &Zeroizing<[u8; 32]>, data: &ApiKeyData) -> Result<ApiKeyData, String> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    let plaintext = serde_json::to_vec(data)
+        .map_err(|e| format!("Failed to serialize API key data: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_ref())
+        .map_err(|e| format!("Failed to seal API key data: {}", e))?;
+
+    let payload = SealedPayload {
+        nonce: base64_encode(&nonce_bytes),
+        ciphertext: base64_encode(&ciphertext),
+    };
+    let sealed_json = serde_json::to_string(&payload)
+        .map_err(|e| format!("Failed to serialize sealed payload: {}", e))?;
+
+    Ok(ApiKeyData {
+        provider: data.provider.clone(),
+        api_key: sealed_json,
+        custom_endpoint: None,
+    })
+}
+
+/// Reverses [`seal`], decrypting the envelope stored in `sealed.api_key`.
+pub fn unseal(key: &Zeroizing<[u8; 32]>, sealed: &ApiKeyData) -> Result<ApiKeyData, String> {
+    let payload: SealedPayload = serde_json::from_str(&sealed.api_key)
+        .map_err(|e| format!("Failed to parse sealed payload: {}", e))?;
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+    let nonce_bytes = base64_decode(&payload.nonce)?;
+    if nonce_bytes.len() != NONCE_LEN {
+        return Err(format!(
+            "Corrupt sealed payload for {}: expected a {}-byte nonce, got {}",
+            sealed.provider,
+            NONCE_LEN,
+            nonce_bytes.len()
+        ));
+    }
+    let nonce = XNonce::from_slice(&nonce_bytes);
+    let ciphertext = base64_decode(&payload.ciphertext)?;
+
+    let plaintext = cipher
+        .decrypt(nonce, ciphertext.as_ref())
+        .map_err(|e| format!("Failed to unseal API key data for {}: {}", sealed.provider, e))?;
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| format!("Failed to deserialize API key data: {}", e))
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64_decode(s: &str) -> Result<Vec<u8>, String> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(s)
+        .map_err(|e| format!("Failed to decode base64 value: {}", e))
+}
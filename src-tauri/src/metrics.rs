@@ -0,0 +1,234 @@
+//! Opt-in metrics: backend supervisor state and secure-storage operation
+//! counters, exposed in Prometheus text format and pushed to the frontend
+//! as Tauri events for an in-app diagnostics panel.
+//!
+//! Counters are cheap atomics and are always collected; only *exposing*
+//! them costs anything, so that's what's gated behind the `ECHOV2_METRICS_ADDR`
+//! env var. Unset, this module is inert.
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tokio::time::sleep;
+
+use crate::supervisor::BackendStatus;
+
+const ENV_METRICS_ADDR: &str = "ECHOV2_METRICS_ADDR";
+const EVENT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const CONNECTION_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct SecretStoreCounters {
+    set: AtomicU64,
+    get: AtomicU64,
+    delete: AtomicU64,
+    list: AtomicU64,
+    errors: AtomicU64,
+}
+
+pub enum SecretOp {
+    Set,
+    Get,
+    Delete,
+    List,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    restart_count: AtomicU64,
+    last_health_check_latency_ms: AtomicU64,
+    status: Mutex<BackendStatus>,
+    ready_since: Mutex<Option<Instant>>,
+    secret_store: SecretStoreCounters,
+}
+
+#[derive(Serialize, Clone)]
+pub struct MetricsSnapshot {
+    pub backend_status: BackendStatus,
+    pub backend_restart_count: u64,
+    pub backend_uptime_seconds: u64,
+    pub backend_last_health_check_latency_ms: u64,
+    pub secret_store_set_total: u64,
+    pub secret_store_get_total: u64,
+    pub secret_store_delete_total: u64,
+    pub secret_store_list_total: u64,
+    pub secret_store_errors_total: u64,
+}
+
+impl Metrics {
+    pub fn record_backend_status(&self, status: BackendStatus) {
+        *self.status.lock().unwrap() = status;
+        let mut ready_since = self.ready_since.lock().unwrap();
+        match status {
+            BackendStatus::Ready => *ready_since = Some(Instant::now()),
+            _ => *ready_since = None,
+        }
+    }
+
+    pub fn record_restart(&self) {
+        self.restart_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_health_check_latency(&self, latency: Duration) {
+        self.last_health_check_latency_ms
+            .store(latency.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_secret_op(&self, op: SecretOp, succeeded: bool) {
+        let counter = match op {
+            SecretOp::Set => &self.secret_store.set,
+            SecretOp::Get => &self.secret_store.get,
+            SecretOp::Delete => &self.secret_store.delete,
+            SecretOp::List => &self.secret_store.list,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            self.secret_store.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let uptime = self
+            .ready_since
+            .lock()
+            .unwrap()
+            .map(|since| since.elapsed().as_secs())
+            .unwrap_or(0);
+
+        MetricsSnapshot {
+            backend_status: *self.status.lock().unwrap(),
+            backend_restart_count: self.restart_count.load(Ordering::Relaxed),
+            backend_uptime_seconds: uptime,
+            backend_last_health_check_latency_ms: self
+                .last_health_check_latency_ms
+                .load(Ordering::Relaxed),
+            secret_store_set_total: self.secret_store.set.load(Ordering::Relaxed),
+            secret_store_get_total: self.secret_store.get.load(Ordering::Relaxed),
+            secret_store_delete_total: self.secret_store.delete.load(Ordering::Relaxed),
+            secret_store_list_total: self.secret_store.list.load(Ordering::Relaxed),
+            secret_store_errors_total: self.secret_store.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn render_prometheus(&self) -> String {
+        let s = self.snapshot();
+        let status_code = match s.backend_status {
+            BackendStatus::Stopped => 0,
+            BackendStatus::Starting => 1,
+            BackendStatus::Ready => 2,
+            BackendStatus::Crashed => 3,
+            BackendStatus::GivingUp => 4,
+        };
+
+        let mut out = String::new();
+        out.push_str("# HELP echov2_backend_status Current backend status (0=stopped,1=starting,2=ready,3=crashed,4=giving-up)\n");
+        out.push_str("# TYPE echov2_backend_status gauge\n");
+        out.push_str(&format!("echov2_backend_status {}\n", status_code));
+
+        out.push_str("# HELP echov2_backend_restart_count Number of times the supervisor has restarted the backend\n");
+        out.push_str("# TYPE echov2_backend_restart_count counter\n");
+        out.push_str(&format!("echov2_backend_restart_count {}\n", s.backend_restart_count));
+
+        out.push_str("# HELP echov2_backend_uptime_seconds Seconds since the backend last became ready\n");
+        out.push_str("# TYPE echov2_backend_uptime_seconds gauge\n");
+        out.push_str(&format!("echov2_backend_uptime_seconds {}\n", s.backend_uptime_seconds));
+
+        out.push_str("# HELP echov2_backend_last_health_check_latency_ms Latency of the most recent successful health check\n");
+        out.push_str("# TYPE echov2_backend_last_health_check_latency_ms gauge\n");
+        out.push_str(&format!(
+            "echov2_backend_last_health_check_latency_ms {}\n",
+            s.backend_last_health_check_latency_ms
+        ));
+
+        out.push_str("# HELP echov2_secret_store_operations_total Secure storage operations by kind\n");
+        out.push_str("# TYPE echov2_secret_store_operations_total counter\n");
+        out.push_str(&format!(
+            "echov2_secret_store_operations_total{{operation=\"set\"}} {}\n",
+            s.secret_store_set_total
+        ));
+        out.push_str(&format!(
+            "echov2_secret_store_operations_total{{operation=\"get\"}} {}\n",
+            s.secret_store_get_total
+        ));
+        out.push_str(&format!(
+            "echov2_secret_store_operations_total{{operation=\"delete\"}} {}\n",
+            s.secret_store_delete_total
+        ));
+        out.push_str(&format!(
+            "echov2_secret_store_operations_total{{operation=\"list\"}} {}\n",
+            s.secret_store_list_total
+        ));
+        out.push_str(&format!(
+            "echov2_secret_store_operations_total{{operation=\"error\"}} {}\n",
+            s.secret_store_errors_total
+        ));
+
+        out
+    }
+}
+
+fn metrics_addr() -> Option<String> {
+    std::env::var(ENV_METRICS_ADDR).ok()
+}
+
+/// Binds a tiny `/metrics` listener if `ECHOV2_METRICS_ADDR` is set. Any
+/// request gets the same Prometheus text response; there's only one route.
+pub fn maybe_start_http_server(metrics: Arc<Metrics>) {
+    let Some(addr) = metrics_addr() else {
+        return;
+    };
+
+    std::thread::spawn(move || {
+        let listener = match TcpListener::bind(&addr) {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Failed to bind metrics listener on {}: {}", addr, e);
+                return;
+            }
+        };
+
+        println!("Metrics endpoint listening on http://{}/metrics", addr);
+        for stream in listener.incoming().flatten() {
+            // Handle each connection on its own thread: a client that opens
+            // the socket but sends nothing (or sends slowly) must not be
+            // able to wedge the listener and starve every other scrape. The
+            // read timeout is a second line of defense for the same client
+            // once it's on its own thread.
+            let metrics = metrics.clone();
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let _ = stream.set_read_timeout(Some(CONNECTION_READ_TIMEOUT));
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+
+                let body = metrics.render_prometheus();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+}
+
+/// Periodically pushes a `metrics-snapshot` event to the frontend, opt-in
+/// under the same `ECHOV2_METRICS_ADDR` flag as the HTTP listener.
+pub fn maybe_start_event_emitter(app: AppHandle, metrics: Arc<Metrics>) {
+    if metrics_addr().is_none() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            let _ = app.emit_all("metrics-snapshot", metrics.snapshot());
+            sleep(EVENT_POLL_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,205 @@
+//! Encrypted-file `SecretStore`, for headless/CI hosts with no Secret
+//! Service daemon (and thus no working OS keyring).
+//!
+//! All records live in a single file as JSON. The file key is derived from
+//! a user passphrase via Argon2, with a random salt generated once and
+//! stored alongside the ciphertext. Each record is sealed independently
+//! with XChaCha20-Poly1305 under a fresh random nonce, so touching one
+//! provider's key doesn't require re-encrypting the others.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use super::{ApiKeyData, SecretStore};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const ENV_PASSPHRASE: &str = "ECHOV2_FILE_PASSPHRASE";
+const ENV_PATH: &str = "ECHOV2_FILE_STORE_PATH";
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct EncryptedRecord {
+    nonce: String,      // base64
+    ciphertext: String, // base64
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct FileEnvelope {
+    salt: String, // base64
+    records: HashMap<String, EncryptedRecord>,
+}
+
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    passphrase: String,
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: PathBuf, passphrase: String) -> Self {
+        Self {
+            path,
+            passphrase,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Build a store from `ECHOV2_FILE_STORE_PATH`/`ECHOV2_FILE_PASSPHRASE`,
+    /// falling back to a path under the app's config directory. The
+    /// passphrase has no safe default: an unset `ECHOV2_FILE_PASSPHRASE`
+    /// fails closed rather than quietly deriving the file key from an empty
+    /// string, which would produce a file that looks encrypted but isn't
+    /// protected by anything secret.
+    pub fn from_env() -> Result<Self, String> {
+        let path = std::env::var(ENV_PATH)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| default_store_path());
+        let passphrase = std::env::var(ENV_PASSPHRASE).map_err(|_| {
+            format!(
+                "{} must be set to use the encrypted file secret backend",
+                ENV_PASSPHRASE
+            )
+        })?;
+        Ok(Self::new(path, passphrase))
+    }
+
+    fn derive_key(&self, salt: &[u8]) -> Result<Key, String> {
+        let mut key_bytes = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key_bytes)
+            .map_err(|e| format!("Failed to derive key from passphrase: {}", e))?;
+        Ok(*Key::from_slice(&key_bytes))
+    }
+
+    fn load_envelope(&self) -> Result<FileEnvelope, String> {
+        if !self.path.exists() {
+            let mut salt = [0u8; SALT_LEN];
+            rand::thread_rng().fill_bytes(&mut salt);
+            return Ok(FileEnvelope {
+                salt: base64_encode(&salt),
+                records: HashMap::new(),
+            });
+        }
+
+        let raw = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read secret store file: {}", e))?;
+        serde_json::from_str(&raw).map_err(|e| format!("Failed to parse secret store file: {}", e))
+    }
+
+    /// Writes the whole envelope via a temp file + rename, the same pattern
+    /// `master_key::persist_salt` uses, so a crash/power-loss mid-write can't
+    /// truncate or corrupt every provider's record, just the would-be update.
+    fn save_envelope(&self, envelope: &FileEnvelope) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create secret store directory: {}", e))?;
+        }
+        let serialized = serde_json::to_string_pretty(envelope)
+            .map_err(|e| format!("Failed to serialize secret store file: {}", e))?;
+
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serialized)
+            .map_err(|e| format!("Failed to write secret store file: {}", e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace secret store file: {}", e))
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn set(&self, provider: &str, data: &ApiKeyData) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap();
+        let mut envelope = self.load_envelope()?;
+        let salt = base64_decode(&envelope.salt)?;
+        let key = self.derive_key(&salt)?;
+        let cipher = XChaCha20Poly1305::new(&key);
+
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce_bytes);
+        let nonce = XNonce::from_slice(&nonce_bytes);
+
+        let plaintext = serde_json::to_vec(data)
+            .map_err(|e| format!("Failed to serialize API key data: {}", e))?;
+        let ciphertext = cipher
+            .encrypt(nonce, plaintext.as_ref())
+            .map_err(|e| format!("Failed to encrypt API key data: {}", e))?;
+
+        envelope.records.insert(
+            provider.to_string(),
+            EncryptedRecord {
+                nonce: base64_encode(&nonce_bytes),
+                ciphertext: base64_encode(&ciphertext),
+            },
+        );
+
+        self.save_envelope(&envelope)
+    }
+
+    fn get(&self, provider: &str) -> Result<Option<ApiKeyData>, String> {
+        let _guard = self.lock.lock().unwrap();
+        let envelope = self.load_envelope()?;
+        let Some(record) = envelope.records.get(provider) else {
+            return Ok(None);
+        };
+
+        let salt = base64_decode(&envelope.salt)?;
+        let key = self.derive_key(&salt)?;
+        let cipher = XChaCha20Poly1305::new(&key);
+
+        let nonce_bytes = base64_decode(&record.nonce)?;
+        if nonce_bytes.len() != NONCE_LEN {
+            return Err(format!(
+                "Corrupt secret store record for {}: expected a {}-byte nonce, got {}",
+                provider,
+                NONCE_LEN,
+                nonce_bytes.len()
+            ));
+        }
+        let nonce = XNonce::from_slice(&nonce_bytes);
+        let ciphertext = base64_decode(&record.ciphertext)?;
+
+        let plaintext = cipher
+            .decrypt(nonce, ciphertext.as_ref())
+            .map_err(|e| format!("Failed to decrypt API key data for {}: {}", provider, e))?;
+        let data: ApiKeyData = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Failed to deserialize API key data: {}", e))?;
+        Ok(Some(data))
+    }
+
+    fn delete(&self, provider: &str) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap();
+        let mut envelope = self.load_envelope()?;
+        envelope.records.remove(provider);
+        self.save_envelope(&envelope)
+    }
+
+    fn list(&self) -> Result<Vec<String>, String> {
+        let _guard = self.lock.lock().unwrap();
+        let envelope = self.load_envelope()?;
+        Ok(envelope.records.keys().cloned().collect())
+    }
+}
+
+fn default_store_path() -> PathBuf {
+    let base = dirs::config_dir().unwrap_or_else(std::env::temp_dir);
+    Path::new(&base).join("echov2").join("secrets.enc.json")
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64_decode(s: &str) -> Result<Vec<u8>, String> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(s)
+        .map_err(|e| format!("Failed to decode base64 value: {}", e))
+}
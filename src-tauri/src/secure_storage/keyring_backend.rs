@@ -0,0 +1,108 @@
+//! OS keyring backend (the original behavior, extracted behind `SecretStore`).
+//!
+//! The keyring has no enumeration API, so a dedicated `api_key_index` entry
+//! tracks the set of providers that have been stored. This also lets users
+//! register custom/self-hosted providers (`groq`, `together`, a local
+//! gateway, ...) instead of being limited to the handful of built-in names.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use keyring::Entry;
+
+use super::{ApiKeyData, SecretStore, API_KEY_PREFIX, SERVICE_NAME};
+
+const INDEX_KEY_NAME: &str = "api_key_index";
+
+pub struct KeyringStore {
+    // Guards the index's load-mutate-save sequence. `SecretStore` is held as
+    // an `Arc<dyn SecretStore>` in Tauri managed state and commands run
+    // concurrently, so without this two `set`/`delete` calls for different
+    // providers can race and silently drop one of the index updates.
+    index_lock: Mutex<()>,
+}
+
+impl KeyringStore {
+    pub fn new() -> Self {
+        Self {
+            index_lock: Mutex::new(()),
+        }
+    }
+
+    fn entry(provider: &str) -> Result<Entry, String> {
+        let key_name = format!("{}_{}", API_KEY_PREFIX, provider);
+        Entry::new(SERVICE_NAME, &key_name)
+            .map_err(|e| format!("Failed to create keyring entry for {}: {}", provider, e))
+    }
+
+    fn index_entry() -> Result<Entry, String> {
+        Entry::new(SERVICE_NAME, INDEX_KEY_NAME)
+            .map_err(|e| format!("Failed to create keyring entry for the provider index: {}", e))
+    }
+
+    fn load_index() -> Result<BTreeSet<String>, String> {
+        let entry = Self::index_entry()?;
+        match entry.get_password() {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map_err(|e| format!("Failed to parse provider index: {}", e)),
+            Err(keyring::Error::NoEntry) => Ok(BTreeSet::new()),
+            Err(e) => Err(format!("Failed to read provider index: {}", e)),
+        }
+    }
+
+    fn save_index(index: &BTreeSet<String>) -> Result<(), String> {
+        let entry = Self::index_entry()?;
+        let serialized = serde_json::to_string(index)
+            .map_err(|e| format!("Failed to serialize provider index: {}", e))?;
+        entry
+            .set_password(&serialized)
+            .map_err(|e| format!("Failed to write provider index: {}", e))
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn set(&self, provider: &str, data: &ApiKeyData) -> Result<(), String> {
+        let entry = Self::entry(provider)?;
+        let serialized = serde_json::to_string(data)
+            .map_err(|e| format!("Failed to serialize API key data: {}", e))?;
+        entry
+            .set_password(&serialized)
+            .map_err(|e| format!("Failed to store API key for {}: {}", provider, e))?;
+
+        let _guard = self.index_lock.lock().unwrap();
+        let mut index = Self::load_index()?;
+        index.insert(provider.to_string());
+        Self::save_index(&index)
+    }
+
+    fn get(&self, provider: &str) -> Result<Option<ApiKeyData>, String> {
+        let entry = Self::entry(provider)?;
+        match entry.get_password() {
+            Ok(password) => {
+                let data: ApiKeyData = serde_json::from_str(&password)
+                    .map_err(|e| format!("Failed to deserialize API key data: {}", e))?;
+                Ok(Some(data))
+            }
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Failed to retrieve API key for {}: {}", provider, e)),
+        }
+    }
+
+    fn delete(&self, provider: &str) -> Result<(), String> {
+        let entry = Self::entry(provider)?;
+        match entry.delete_password() {
+            Ok(()) => {}
+            Err(keyring::Error::NoEntry) => {} // Already deleted
+            Err(e) => return Err(format!("Failed to delete API key for {}: {}", provider, e)),
+        }
+
+        let _guard = self.index_lock.lock().unwrap();
+        let mut index = Self::load_index()?;
+        index.remove(provider);
+        Self::save_index(&index)
+    }
+
+    fn list(&self) -> Result<Vec<String>, String> {
+        Ok(Self::load_index()?.into_iter().collect())
+    }
+}
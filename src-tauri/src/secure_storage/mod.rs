@@ -0,0 +1,50 @@
+//! Pluggable secret storage.
+//!
+//! `store_api_key`/`get_api_key`/`delete_api_key` used to talk to the OS
+//! keyring directly, which falls over on headless boxes with no Secret
+//! Service daemon running. The `SecretStore` trait lets us swap in an
+//! encrypted-file backend for those environments while keeping the OS
+//! keyring as the default on desktop.
+
+pub mod encrypted_file;
+pub mod keyring_backend;
+
+use serde::{Deserialize, Serialize};
+
+pub const SERVICE_NAME: &str = "com.echov2.app";
+pub const API_KEY_PREFIX: &str = "api_key";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiKeyData {
+    pub provider: String,
+    pub api_key: String,
+    pub custom_endpoint: Option<String>,
+}
+
+/// A backend capable of storing provider API keys.
+///
+/// Implementations must be safe to share across the async runtime, since a
+/// single instance is held in Tauri managed state for the life of the app.
+pub trait SecretStore: Send + Sync {
+    fn set(&self, provider: &str, data: &ApiKeyData) -> Result<(), String>;
+    fn get(&self, provider: &str) -> Result<Option<ApiKeyData>, String>;
+    fn delete(&self, provider: &str) -> Result<(), String>;
+    fn list(&self) -> Result<Vec<String>, String>;
+}
+
+/// Env var used to pick the active backend. Anything other than `"file"`
+/// falls back to the OS keyring, which remains the default.
+const BACKEND_ENV_VAR: &str = "ECHOV2_SECRET_BACKEND";
+
+/// Build the configured `SecretStore` for this process.
+///
+/// Selection happens once at startup: `ECHOV2_SECRET_BACKEND=file` picks the
+/// encrypted-file store, anything else (including unset) picks the OS
+/// keyring. Fails if the selected backend can't be constructed, e.g. the
+/// file backend with no passphrase configured.
+pub fn backend_from_env() -> Result<Box<dyn SecretStore>, String> {
+    match std::env::var(BACKEND_ENV_VAR).as_deref() {
+        Ok("file") => Ok(Box::new(encrypted_file::EncryptedFileStore::from_env()?)),
+        _ => Ok(Box::new(keyring_backend::KeyringStore::new())),
+    }
+}
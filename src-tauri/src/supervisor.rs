@@ -0,0 +1,382 @@
+//! Supervises the backend child process.
+//!
+//! `start_backend` used to spawn the Python/bundled backend once and never
+//! look at it again, so a mid-session crash silently left the app without a
+//! backend. This module owns the `Child`, streams its stdout/stderr, and
+//! restarts it with exponential backoff (up to a retry ceiling) when it
+//! exits unexpectedly, reporting status the whole way via [`BackendEvents`]
+//! so both the Tauri commands and the headless CLI can drive it.
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::metrics::Metrics;
+
+const HEALTH_URL: &str = "http://localhost:8000/health";
+const HEALTH_CHECK_ATTEMPTS: u32 = 30;
+const MAX_RESTART_ATTEMPTS: u32 = 6;
+const BASE_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_SECS: u64 = 30;
+const ENV_PID_PATH: &str = "ECHOV2_BACKEND_PID_PATH";
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendStatus {
+    Starting,
+    Ready,
+    Crashed,
+    GivingUp,
+    Stopped,
+}
+
+impl Default for BackendStatus {
+    fn default() -> Self {
+        BackendStatus::Stopped
+    }
+}
+
+/// Sink for supervisor events. The Tauri commands forward these as
+/// `backend-status`/`backend-log` events; the headless CLI just prints them.
+pub trait BackendEvents: Send + Sync {
+    fn on_status(&self, status: BackendStatus);
+    fn on_log(&self, line: &str);
+}
+
+#[derive(Default)]
+pub struct BackendState {
+    process: Arc<Mutex<Option<Child>>>,
+    status: Arc<Mutex<BackendStatus>>,
+    restart_count: Arc<Mutex<u32>>,
+    stop_requested: Arc<AtomicBool>,
+    // Bumped by every `spawn_supervised` call. A running `run_supervisor`
+    // task captures the generation it was started with and checks it
+    // against this before acting on a `process`/`status` change it's about
+    // to make; if they've diverged, a newer call has superseded it (e.g.
+    // `restart_backend`'s `stop` + `spawn_supervised` racing the outgoing
+    // task's own poll loop), so it exits quietly instead of fighting the
+    // new task over the same `process` slot.
+    generation: Arc<AtomicU64>,
+}
+
+impl BackendState {
+    pub fn status(&self) -> BackendStatus {
+        *self.status.lock().unwrap()
+    }
+
+    pub fn restart_count(&self) -> u32 {
+        *self.restart_count.lock().unwrap()
+    }
+}
+
+/// True if no newer `spawn_supervised` call has superseded this task.
+fn is_current(state: &BackendState, my_generation: u64) -> bool {
+    state.generation.load(Ordering::SeqCst) == my_generation
+}
+
+fn set_status(events: &dyn BackendEvents, metrics: &Metrics, state: &BackendState, status: BackendStatus) {
+    *state.status.lock().unwrap() = status;
+    metrics.record_backend_status(status);
+    events.on_status(status);
+}
+
+fn locate_backend_command() -> Result<Command, String> {
+    if cfg!(debug_assertions) {
+        let backend_dir = std::env::current_exe()
+            .map_err(|e| e.to_string())?
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("backend");
+
+        let mut cmd = Command::new("python");
+        cmd.arg("main.py").current_dir(backend_dir);
+        Ok(cmd)
+    } else {
+        let app_dir = std::env::current_exe()
+            .map_err(|e| e.to_string())?
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        let backend_executable = app_dir.join("echov2-backend");
+
+        if !backend_executable.exists() {
+            return Err(format!(
+                "Backend executable not found at: {:?}",
+                backend_executable
+            ));
+        }
+
+        Ok(Command::new(backend_executable))
+    }
+}
+
+fn spawn_child() -> Result<Child, String> {
+    let mut cmd = locate_backend_command()?;
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    cmd.spawn()
+        .map_err(|e| format!("Failed to spawn backend process: {}", e))
+}
+
+/// Drains the child's stdout/stderr on dedicated threads (they block on
+/// blocking reads, so they don't belong on the async runtime) and forwards
+/// each line on to `events`.
+fn stream_output(events: Arc<dyn BackendEvents>, child: &mut Child) {
+    if let Some(stdout) = child.stdout.take() {
+        let events = events.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().flatten() {
+                events.on_log(&line);
+            }
+        });
+    }
+
+    if let Some(stderr) = child.stderr.take() {
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().flatten() {
+                events.on_log(&line);
+            }
+        });
+    }
+}
+
+async fn wait_for_health(metrics: &Metrics) -> Result<(), String> {
+    for _ in 0..HEALTH_CHECK_ATTEMPTS {
+        let started = Instant::now();
+        let healthy = check_health_once().await;
+        if healthy {
+            metrics.record_health_check_latency(started.elapsed());
+            return Ok(());
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    Err("Backend failed to start within timeout".to_string())
+}
+
+async fn check_health_once() -> bool {
+    let client = reqwest::Client::new();
+    matches!(client.get(HEALTH_URL).send().await, Ok(response) if response.status().is_success())
+}
+
+/// Sleeps for the next exponential-backoff interval and bumps the restart
+/// counter, or flips to `GivingUp` and returns `false` once the retry
+/// ceiling is hit.
+async fn backoff_or_give_up(
+    events: &dyn BackendEvents,
+    metrics: &Metrics,
+    state: &BackendState,
+    attempt: &mut u32,
+) -> bool {
+    *attempt += 1;
+    *state.restart_count.lock().unwrap() = *attempt;
+    metrics.record_restart();
+
+    if *attempt > MAX_RESTART_ATTEMPTS {
+        set_status(events, metrics, state, BackendStatus::GivingUp);
+        remove_pid_file();
+        return false;
+    }
+
+    let backoff = BASE_BACKOFF_SECS
+        .saturating_mul(1 << (*attempt - 1))
+        .min(MAX_BACKOFF_SECS);
+    sleep(Duration::from_secs(backoff)).await;
+    true
+}
+
+async fn run_supervisor(
+    events: Arc<dyn BackendEvents>,
+    metrics: Arc<Metrics>,
+    state: Arc<BackendState>,
+    my_generation: u64,
+) {
+    let mut attempt = 0u32;
+
+    loop {
+        if !is_current(&state, my_generation) {
+            return;
+        }
+
+        set_status(events.as_ref(), metrics.as_ref(), &state, BackendStatus::Starting);
+
+        let mut child = match spawn_child() {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Failed to spawn backend: {}", e);
+                if !backoff_or_give_up(events.as_ref(), metrics.as_ref(), &state, &mut attempt).await {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        let pid = child.id();
+        stream_output(events.clone(), &mut child);
+        *state.process.lock().unwrap() = Some(child);
+        write_pid_file(pid);
+
+        match wait_for_health(metrics.as_ref()).await {
+            Ok(()) => {
+                attempt = 0;
+                *state.restart_count.lock().unwrap() = 0;
+                set_status(events.as_ref(), metrics.as_ref(), &state, BackendStatus::Ready);
+            }
+            Err(e) => {
+                eprintln!("Backend health check failed: {}", e);
+
+                // The child may just be slow, wedged, or listening on the
+                // wrong port; left alone it would never exit on its own, so
+                // status would stay `Starting` forever with no restart ever
+                // attempted. Kill it and go through the normal crash path.
+                if let Some(mut child) = state.process.lock().unwrap().take() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                remove_pid_file();
+
+                set_status(events.as_ref(), metrics.as_ref(), &state, BackendStatus::Crashed);
+
+                if state.stop_requested.load(Ordering::SeqCst) {
+                    set_status(events.as_ref(), metrics.as_ref(), &state, BackendStatus::Stopped);
+                    return;
+                }
+
+                if !backoff_or_give_up(events.as_ref(), metrics.as_ref(), &state, &mut attempt).await {
+                    return;
+                }
+                continue;
+            }
+        }
+
+        // Poll with `try_wait()` rather than blocking on `child.wait()` while
+        // holding `process`'s lock: `stop()`/`restart_backend` need that same
+        // lock to reach the child and kill it, and they can only do so before
+        // it exits, so holding the lock across a blocking wait is a deadlock.
+        let exit_status = loop {
+            if !is_current(&state, my_generation) {
+                return;
+            }
+
+            let poll_result = {
+                let mut guard = state.process.lock().unwrap();
+                guard.as_mut().map(|child| child.try_wait())
+            };
+
+            match poll_result {
+                Some(Ok(Some(status))) => break Some(status),
+                Some(Ok(None)) => sleep(EXIT_POLL_INTERVAL).await,
+                Some(Err(e)) => {
+                    eprintln!("Failed to poll backend process: {}", e);
+                    break None;
+                }
+                None => break None, // taken by stop()/restart_backend already
+            }
+        };
+
+        *state.process.lock().unwrap() = None;
+
+        if state.stop_requested.load(Ordering::SeqCst) {
+            set_status(events.as_ref(), metrics.as_ref(), &state, BackendStatus::Stopped);
+            remove_pid_file();
+            return;
+        }
+
+        println!("Backend exited ({:?}); supervisor will restart it", exit_status);
+        set_status(events.as_ref(), metrics.as_ref(), &state, BackendStatus::Crashed);
+
+        if !backoff_or_give_up(events.as_ref(), metrics.as_ref(), &state, &mut attempt).await {
+            return;
+        }
+    }
+}
+
+/// Spawns the backend and starts the supervisor loop that restarts it with
+/// exponential backoff if it exits unexpectedly. Used both at startup and
+/// by `restart_backend` for manual restarts.
+pub fn spawn_supervised(events: Arc<dyn BackendEvents>, metrics: Arc<Metrics>, state: Arc<BackendState>) {
+    state.stop_requested.store(false, Ordering::SeqCst);
+    let my_generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    tokio::spawn(async move {
+        run_supervisor(events, metrics, state, my_generation).await;
+    });
+}
+
+/// Stops the supervisor loop and kills the current child, if any.
+pub fn stop(state: &BackendState) {
+    state.stop_requested.store(true, Ordering::SeqCst);
+    if let Some(mut child) = state.process.lock().unwrap().take() {
+        println!("Stopping backend process...");
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    remove_pid_file();
+}
+
+fn pid_file_path() -> PathBuf {
+    if let Ok(path) = std::env::var(ENV_PID_PATH) {
+        return PathBuf::from(path);
+    }
+    let base = dirs::config_dir().unwrap_or_else(std::env::temp_dir);
+    Path::new(&base).join("echov2").join("backend.pid")
+}
+
+fn write_pid_file(pid: u32) {
+    let path = pid_file_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(path, pid.to_string());
+}
+
+fn remove_pid_file() {
+    let _ = fs::remove_file(pid_file_path());
+}
+
+fn read_pid_file() -> Option<u32> {
+    fs::read_to_string(pid_file_path()).ok()?.trim().parse().ok()
+}
+
+/// Stops a backend started by a separate `backend start` CLI invocation,
+/// found via the pid file it wrote (there's no in-process supervisor to ask
+/// in this case, since `stop` runs as its own short-lived process).
+pub fn stop_external() -> Result<(), String> {
+    let pid = read_pid_file().ok_or_else(|| "No running backend found (no pid file)".to_string())?;
+
+    #[cfg(unix)]
+    let status = Command::new("kill").arg(pid.to_string()).status();
+    #[cfg(windows)]
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/F"])
+        .status();
+
+    status.map_err(|e| format!("Failed to signal backend process {}: {}", pid, e))?;
+    remove_pid_file();
+    println!("Stopped backend process {}", pid);
+    Ok(())
+}
+
+/// Reports backend status for a separate `backend start` CLI invocation, by
+/// checking the pid file and hitting the health endpoint directly.
+pub async fn query_external_status() -> BackendStatus {
+    if read_pid_file().is_none() {
+        return BackendStatus::Stopped;
+    }
+
+    if check_health_once().await {
+        BackendStatus::Ready
+    } else {
+        BackendStatus::Crashed
+    }
+}